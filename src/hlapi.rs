@@ -44,6 +44,9 @@ pub enum ConvErr {
 
     FoundInteriorNulByte { pos: usize, bytes: Option<Vec<u8>> },
     NotNulTerminated,
+
+    MmapFailed { errno: i32 },
+    CircularList,
 }
 
 
@@ -187,6 +190,15 @@ pub mod elisp2native {
         }
     }
 
+    /// Fetch the raw `EmacsVal` at `args[index]`, guarding against a null
+    /// `args` pointer the same way [`pointer`] and [`integer`] do.
+    pub fn arg(_env: *mut EmacsEnv, args: *mut EmacsVal, index: usize)
+              -> ConvResult<EmacsVal> {
+        if args.is_null() { return Err(ConvErr::Nullptr(String::from("args"))) }
+        // TODO: verify that `index` is within bounds
+        unsafe { Ok(*args.offset(index as isize)) }
+    }
+
     pub fn integer(env: *mut EmacsEnv, args: *mut EmacsVal, index: usize)
                    -> ConvResult<i64> {
         if args.is_null() { return Err(ConvErr::Nullptr(String::from("args"))) }
@@ -207,7 +219,30 @@ pub mod elisp2native {
     }
 
 
+    /// Convert an Elisp list into a `Vec<EmacsVal>` by walking `car`/`cdr`
+    /// once, so conversion is linear instead of the quadratic `nth`-in-a-
+    /// loop a singly-linked list would otherwise incur.
     pub fn list(env: *mut EmacsEnv, arg: EmacsVal) -> ConvResult<Vec<EmacsVal>> {
+        list_map(env, arg, |_env, element| Ok(element))
+    }
+
+    /// Like [`list`], but applies the `ConvResult`-returning converter `f`
+    /// to each element during the walk, so callers converting e.g. a list
+    /// of Elisp strings/integers don't have to build a `Vec<EmacsVal>`
+    /// first and then re-iterate over it.
+    ///
+    /// Unlike `(length arg)`, which Emacs uses to reject circular lists
+    /// before this crate ever sees them, a raw `car`/`cdr` walk has no
+    /// built-in cycle detection -- a list built with `setcdr` to point
+    /// back at itself would otherwise hang this loop (and the whole,
+    /// single-threaded, Emacs process) forever. So alongside the `cursor`
+    /// that walks one cell at a time and yields elements, a second `hare`
+    /// cursor walks two cells at a time (Floyd's tortoise-and-hare); if it
+    /// ever lands back on `cursor`, the list is circular.
+    pub fn list_map<T, F>(env: *mut EmacsEnv, arg: EmacsVal, mut f: F)
+                          -> ConvResult<Vec<T>>
+        where F: FnMut(*mut EmacsEnv, EmacsVal) -> ConvResult<T>
+    {
         let nil: EmacsVal = ::native2elisp::symbol(env, "nil")?;
         let is_list: EmacsVal = ::call(env, "listp", &mut [arg]);
         if ::eq(env, is_list, nil)? {
@@ -216,15 +251,22 @@ pub mod elisp2native {
                 got: Some(arg)
             });
         }
-        let length: EmacsVal = ::call(env, "length", &mut [arg]);
-        let length: i64 = int_value(env, length)?;
-        let mut list: Vec<EmacsVal> = vec![];
-        for i in 0 .. length {
-            let index: EmacsVal = ::native2elisp::integer(env, i)?;
-            let element: EmacsVal = ::call(env, "nth", &mut [index, arg]);
-            list.push(element);
+        let mut result: Vec<T> = vec![];
+        let mut cursor: EmacsVal = arg;
+        let mut hare: EmacsVal = arg;
+        while !::is_nil(env, cursor)? {
+            let element: EmacsVal = ::call(env, "car", &mut [cursor]);
+            result.push(f(env, element)?);
+            cursor = ::call(env, "cdr", &mut [cursor]);
+
+            hare = ::call(env, "cdr", &mut [hare]);
+            if ::is_nil(env, hare)? { continue; }
+            hare = ::call(env, "cdr", &mut [hare]);
+            if !::is_nil(env, cursor)? && ::eq(env, cursor, hare)? {
+                return Err(ConvErr::CircularList);
+            }
         }
-        Ok(list)
+        Ok(result)
     }
 
 }
@@ -234,7 +276,7 @@ pub mod native2elisp {
     use emacs_gen::{Dtor, EmacsEnv, EmacsSubr, EmacsVal};
     use hlapi::{ConvErr, ConvResult};
     use libc;
-    use std::ffi::CString;
+    use std::ffi::{CStr, CString};
     use std::os::raw;
 
     pub fn integer(env: *mut EmacsEnv, num: i64) -> ConvResult<EmacsVal> {
@@ -246,21 +288,26 @@ pub mod native2elisp {
         }
     }
 
-    /// Convert a Rust String/&str into an Elisp string.
-    pub fn string<S>(env: *mut EmacsEnv, string: S) -> ConvResult<EmacsVal>
-        where S: Into<Vec<u8>>
-    {
+    /// Convert an already-nul-terminated `&CStr` into an Elisp string with
+    /// no allocation and no `strlen` rescan: both the pointer and the
+    /// length are already known.
+    pub fn cstr(env: *mut EmacsEnv, s: &CStr) -> ConvResult<EmacsVal> {
         unsafe {
-            let string: Vec<u8> = string.into();
-            let cstring = CString::new(string)?;
-            let c_string: *const libc::c_char = cstring.as_ptr();
-            let strlen: usize = libc::strlen(c_string);
             let make_string = (*env).make_string
                 .ok_or(ConvErr::CoreFnMissing(String::from("make_string")))?;
-            Ok(make_string(env, c_string, strlen as isize))
+            Ok(make_string(env, s.as_ptr(), s.to_bytes().len() as isize))
         }
     }
 
+    /// Convert a Rust String/&str into an Elisp string.
+    pub fn string<S>(env: *mut EmacsEnv, string: S) -> ConvResult<EmacsVal>
+        where S: Into<Vec<u8>>
+    {
+        let string: Vec<u8> = string.into();
+        let cstring = CString::new(string)?;
+        cstr(env, &cstring)
+    }
+
     /// Intern a new Elisp symbol.
     pub fn symbol(env: *mut EmacsEnv, name: &str) -> ConvResult<EmacsVal> {
         Ok(call(env, "intern", &mut [string(env, name)?]))
@@ -309,6 +356,232 @@ pub mod native2elisp {
 }
 
 
+/// Zero-copy, shared-memory buffers handed to Elisp as `user_ptr` handles.
+///
+/// A module author calls [`shm::alloc`](self::alloc) once to get a
+/// persistent, mutation-in-place buffer backed by an anonymous
+/// `memfd_create`d + `mmap`ed region, instead of round-tripping whole
+/// strings across the FFI boundary via `copy_string_contents` on every
+/// call (see `elisp2native::string_bytes`).
+///
+/// `memfd_create` is Linux-specific (`SYS_memfd_create` only exists in
+/// `libc` for `linux`/`android` targets), so this whole module is gated
+/// to `target_os = "linux"` rather than breaking the build for module
+/// authors on e.g. macOS.
+#[cfg(target_os = "linux")]
+pub mod shm {
+    use emacs_gen::{EmacsEnv, EmacsVal};
+    use hlapi::{ConvErr, ConvResult, destruct, elisp2native, native2elisp};
+    use libc;
+    use std::ffi::CString;
+    use std::io;
+    use std::os::raw;
+    use std::ptr;
+    use std::slice;
+
+    /// A RAM-backed buffer shared between Emacs and native code. Dropping
+    /// it (via the `user_ptr`'s `Dtor`, see [`destruct`]) `munmap`s the
+    /// region and closes the backing fd.
+    struct ShmBuffer {
+        ptr: *mut u8,
+        fd: raw::c_int,
+        len: usize,
+    }
+
+    impl Drop for ShmBuffer {
+        fn drop(&mut self) {
+            unsafe {
+                libc::munmap(self.ptr as *mut libc::c_void, self.len);
+                libc::close(self.fd);
+            }
+        }
+    }
+
+    /// Allocate a `len`-byte anonymous, RAM-backed buffer and hand Emacs
+    /// a `user_ptr` handle to it.
+    pub fn alloc(env: *mut EmacsEnv, len: usize) -> ConvResult<EmacsVal> {
+        unsafe {
+            let name = CString::new("emacs-shm")?;
+            let fd = libc::syscall(libc::SYS_memfd_create, name.as_ptr(), 0)
+                as raw::c_int;
+            if fd < 0 { return Err(ConvErr::from(io::Error::last_os_error())); }
+            if libc::ftruncate(fd, len as libc::off_t) != 0 {
+                let err = ConvErr::from(io::Error::last_os_error());
+                libc::close(fd);
+                return Err(err);
+            }
+            let ptr = libc::mmap(ptr::null_mut(), len,
+                                 libc::PROT_READ | libc::PROT_WRITE,
+                                 libc::MAP_SHARED, fd, 0);
+            if ptr == libc::MAP_FAILED {
+                let errno = io::Error::last_os_error().raw_os_error().unwrap_or(-1);
+                libc::close(fd);
+                return Err(ConvErr::MmapFailed { errno: errno });
+            }
+            let buffer = ShmBuffer { ptr: ptr as *mut u8, fd: fd, len: len };
+            native2elisp::boxed(env, buffer, destruct::<ShmBuffer>)
+        }
+    }
+
+    /// Reconstruct the buffer's contents as a `&[u8]` from the `user_ptr`
+    /// at `args[index]`, without copying.
+    pub fn as_slice<'a>(env: *mut EmacsEnv, args: *mut EmacsVal, index: usize)
+                        -> ConvResult<&'a [u8]> {
+        let buffer: &ShmBuffer = elisp2native::mut_ref(env, args, index)?;
+        Ok(unsafe { slice::from_raw_parts(buffer.ptr, buffer.len) })
+    }
+
+    /// Like [`as_slice`], but mutable, so callers can write into the
+    /// buffer in place.
+    pub fn as_mut_slice<'a>(env: *mut EmacsEnv, args: *mut EmacsVal, index: usize)
+                            -> ConvResult<&'a mut [u8]> {
+        let buffer: &mut ShmBuffer = elisp2native::mut_ref(env, args, index)?;
+        Ok(unsafe { slice::from_raw_parts_mut(buffer.ptr, buffer.len) })
+    }
+
+    emacs_subrs! {
+        shm_alloc(env, nargs, args, data, tag) {
+            let len = elisp2native::integer(env, args, 0)?;
+            alloc(env, len as usize)
+        };
+        // `shm_read_byte`/`shm_write_byte` are a minimal single-element API,
+        // useful mostly for tests and for code that genuinely needs to poke
+        // one offset. Real multi-megabyte payloads should go through
+        // `shm_read_range`/`shm_write_string` below, which move the whole
+        // range in a single FFI call.
+        shm_read_byte(env, nargs, args, data, tag) {
+            let index = elisp2native::integer(env, args, 1)?;
+            let bytes = as_slice(env, args, 0)?;
+            let byte = *bytes.get(index as usize).ok_or_else(
+                || ConvErr::Other(format!("{} index {} out of bounds", tag, index))
+            )?;
+            native2elisp::integer(env, byte as i64)
+        };
+        shm_write_byte(env, nargs, args, data, tag) {
+            let index = elisp2native::integer(env, args, 1)?;
+            let value = elisp2native::integer(env, args, 2)?;
+            let bytes = as_mut_slice(env, args, 0)?;
+            let slot = bytes.get_mut(index as usize).ok_or_else(
+                || ConvErr::Other(format!("{} index {} out of bounds", tag, index))
+            )?;
+            *slot = value as u8;
+            native2elisp::symbol(env, "t")
+        };
+        // Bulk accessors: these are the ones multi-megabyte payloads should
+        // actually use, since they move the whole `start..end` range in one
+        // FFI call instead of one call per byte.
+        shm_read_range(env, nargs, args, data, tag) {
+            let start = elisp2native::integer(env, args, 1)? as usize;
+            let end = elisp2native::integer(env, args, 2)? as usize;
+            let bytes = as_slice(env, args, 0)?;
+            let range = bytes.get(start .. end).ok_or_else(
+                || ConvErr::Other(format!("{} range {}..{} out of bounds", tag, start, end))
+            )?;
+            native2elisp::string(env, range.to_vec())
+        };
+        shm_write_string(env, nargs, args, data, tag) {
+            let start = elisp2native::integer(env, args, 1)? as usize;
+            let text = elisp2native::string(env, elisp2native::arg(env, args, 2)?)?;
+            let bytes = text.into_bytes();
+            let end = start.checked_add(bytes.len()).ok_or_else(
+                || ConvErr::Other(format!("{} range {}..+{} out of bounds", tag, start, bytes.len()))
+            )?;
+            let buffer = as_mut_slice(env, args, 0)?;
+            let slot = buffer.get_mut(start .. end).ok_or_else(
+                || ConvErr::Other(format!("{} range {}..{} out of bounds", tag, start, end))
+            )?;
+            slot.copy_from_slice(&bytes);
+            native2elisp::integer(env, bytes.len() as i64)
+        };
+    }
+}
+
+
+/// Maps [`ConvErr`] (and Rust panics) onto real Emacs error conditions via
+/// `non_local_exit_signal`, so Elisp's `condition-case` can distinguish
+/// e.g. an I/O error from a type mismatch instead of seeing a plain string.
+pub mod signal {
+    use emacs_gen::{EmacsEnv, EmacsVal};
+    use hlapi::{ConvErr, ConvResult, native2elisp};
+
+    /// The stable Elisp condition symbol a given [`ConvErr`] variant is
+    /// signalled as.
+    fn condition(err: &ConvErr) -> &'static str {
+        match *err {
+            ConvErr::WrongEmacsValueType { .. } => "wrong-type-argument",
+            ConvErr::InvalidArgCount(_) => "wrong-number-of-arguments",
+            ConvErr::IoErr { .. } => "my-module-io-error",
+            ConvErr::RegexSyntaxErr(_) | ConvErr::RegexTooLarge(_) =>
+                "my-module-regex-error",
+            ConvErr::FromUtf8Error { .. } | ConvErr::Utf8Error { .. } =>
+                "my-module-utf8-error",
+            ConvErr::ParseIntError(_) => "my-module-parse-int-error",
+            ConvErr::FoundInteriorNulByte { .. } | ConvErr::NotNulTerminated =>
+                "my-module-nul-byte-error",
+            ConvErr::MmapFailed { .. } => "my-module-mmap-error",
+            ConvErr::CircularList => "my-module-circular-list-error",
+            ConvErr::CoreFnMissing(_) => "my-module-core-fn-missing-error",
+            ConvErr::Nullptr(_) => "my-module-nullptr-error",
+            ConvErr::FailedToFetchLength | ConvErr::FailedToCopy =>
+                "my-module-copy-error",
+            ConvErr::Other(_) => "my-module-error",
+        }
+    }
+
+    /// Signal `err` as an Emacs error condition. The data is a one-element
+    /// list holding the `Debug` rendering of `err` (variant name and field
+    /// values) as a string; the fields are not passed as separate Elisp
+    /// values.
+    ///
+    /// Deliberately infallible: this is the last thing the `emacs_subrs!`
+    /// macro does before returning across the `extern "C"` boundary, and it
+    /// already runs outside `catch_unwind`, so nothing here may panic. If
+    /// the condition/data can't be built, or `non_local_exit_signal` itself
+    /// isn't available, the error is dropped on the floor rather than
+    /// risking an unwind across FFI.
+    pub fn signal(env: *mut EmacsEnv, err: ConvErr) {
+        let raised = native2elisp::symbol(env, condition(&err)).and_then(|symbol| {
+            native2elisp::string_list(env, &[format!("{:?}", err)])
+                .map(|data| (symbol, data))
+        });
+        if let Ok((symbol, data)) = raised {
+            let _ = raise(env, symbol, data);
+        }
+    }
+
+    /// Signal that a Rust panic occurred, carrying the panic message as
+    /// data. Infallible for the same reason as [`signal`].
+    pub fn signal_panic(env: *mut EmacsEnv, msg: String) {
+        let raised = native2elisp::symbol(env, "my-module-panic").and_then(|symbol| {
+            native2elisp::string_list(env, &[msg]).map(|data| (symbol, data))
+        });
+        if let Ok((symbol, data)) = raised {
+            let _ = raise(env, symbol, data);
+        }
+    }
+
+    fn raise(env: *mut EmacsEnv, symbol: EmacsVal, data: EmacsVal)
+            -> ConvResult<()> {
+        // A `condition-case` handler only matches a condition symbol that
+        // has an `error-conditions` property, so the module's own
+        // conditions are defined (as children of `error`) on first use.
+        let error_conditions = native2elisp::symbol(env, "error-conditions")?;
+        let conditions = ::call(env, "get", &mut [symbol, error_conditions]);
+        if ::is_nil(env, conditions)? {
+            let message = native2elisp::string(env, "Emacs module error")?;
+            ::call(env, "define-error", &mut [symbol, message]);
+        }
+        unsafe {
+            let non_local_exit_signal = (*env).non_local_exit_signal.ok_or_else(
+                || ConvErr::CoreFnMissing(String::from("non_local_exit_signal"))
+            )?;
+            non_local_exit_signal(env, symbol, data);
+        }
+        Ok(())
+    }
+}
+
+
 /// A μDSL to cut away boilerplate when defining Emacs subrs,
 /// which are of course defined in Rust rather than C here.
 /// One thing enforced at compile time is that some semblance
@@ -326,12 +599,14 @@ macro_rules! emacs_subrs {
                                        -> EmacsVal {
                 // NOTE: The inner `fun` fn provides type checking for Emacs
                 // subrs -- especially their output -- while also allowing each
-                // subr to just use `?` for error handling. This is much nicer
-                // than any alternative would be. It does mean that those errors
-                // need to be dealt with here. For now it calls `expect()` which
-                // still means a panic. However at least the cause should be
-                // clear because of a useful backtrace, as well as proper error
-                // handling in the subrs themselves. This in turn aids debugging.
+                // subr to just use `?` for error handling. A `ConvErr` coming
+                // back out of `fun` is signalled to Elisp as a real error
+                // condition (see `hlapi::signal`) rather than being turned
+                // into a plain string, so `condition-case` can distinguish
+                // error kinds. `fun` is additionally run under
+                // `catch_unwind` so a Rust panic inside a subr is signalled
+                // the same way instead of unwinding across the C boundary
+                // and aborting Emacs.
                 //
                 // Inlining the inner fn means there's no runtime penalty at the
                 // cost of slightly higher compile times.
@@ -343,14 +618,28 @@ macro_rules! emacs_subrs {
                               $tag: &str) -> ConvResult<EmacsVal> { $body }
 
                 let $tag = format!("[{}]", stringify!($name));
-                match fun($env, $nargs, $args, $data, &$tag) {
-                    Ok(value) => value,
-                    Err(conv_err) => {
-                        let msg = format!("{} ConvErr::{:?}", $tag, conv_err);
-                        $crate::hlapi::native2elisp::string($env, msg)
-                        // TODO: implement sans panic using the ?-operator
-                            .expect("Error string creation failed")
-                    }
+                let result = ::std::panic::catch_unwind(
+                    ::std::panic::AssertUnwindSafe(
+                        || fun($env, $nargs, $args, $data, &$tag)
+                    )
+                );
+                // `signal`/`signal_panic` are infallible (see `hlapi::signal`),
+                // so nothing below this point can panic and unwind past
+                // `catch_unwind` across the `extern "C"` boundary.
+                match result {
+                    Ok(Ok(value)) => value,
+                    Ok(Err(conv_err)) => {
+                        $crate::hlapi::signal::signal($env, conv_err);
+                        EmacsVal::default()
+                    },
+                    Err(panic) => {
+                        let msg = panic.downcast_ref::<&str>()
+                            .map(|s| s.to_string())
+                            .or_else(|| panic.downcast_ref::<String>().cloned())
+                            .unwrap_or_else(|| format!("{} panicked", $tag));
+                        $crate::hlapi::signal::signal_panic($env, msg);
+                        EmacsVal::default()
+                    },
                 }
             }
         )*
@@ -414,12 +703,17 @@ pub fn register(env: *mut EmacsEnv,
                 docstring: &str,
                 /* user_ptr: *mut libc::c_void*/)
                 -> ConvResult<EmacsVal> {
-    let doc = CString::new(docstring)?.as_ptr();
+    // `make_function` only borrows the documentation pointer, but the
+    // registered subr -- and thus its documentation -- lives for the rest
+    // of the Emacs session, so the backing `CString` is leaked here rather
+    // than dropped at the end of this statement (which left a dangling
+    // pointer before).
+    let doc: &'static CString = Box::leak(Box::new(CString::new(docstring)?));
     let func = native2elisp::function(env,
                                       nargs_range.start as isize,
                                       nargs_range.end as isize + 1,
                                       Some(native_sym),
-                                      doc,
+                                      doc.as_ptr(),
                                       ptr::null_mut(/* user_ptr */))?;
     let elisp_symbol = native2elisp::symbol(env, elisp_sym)?;
     call(env, "fset", &mut [elisp_symbol, func]);